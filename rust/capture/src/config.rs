@@ -1,5 +1,6 @@
 use std::{net::SocketAddr, num::NonZeroU32};
 
+use common_logging::LogFormat;
 use envconfig::Envconfig;
 
 #[derive(Envconfig, Clone)]
@@ -35,6 +36,11 @@ pub struct Config {
 
     #[envconfig(default = "true")]
     pub export_prometheus: bool,
+
+    pub metrics_bearer_token: Option<String>,
+
+    #[envconfig(default = "text")]
+    pub log_format: LogFormat,
 }
 
 #[derive(Envconfig, Clone)]
@@ -1,6 +1,8 @@
 use std::time::Duration;
 
-use envconfig::Envconfig;
+use common_config::load_config;
+use common_logging::fmt_layer;
+use common_metrics::setup_panic_hook;
 use opentelemetry::{KeyValue, Value};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::trace::{BatchConfig, RandomIdGenerator, Sampler, Tracer};
@@ -58,12 +60,13 @@ fn init_tracer(sink_url: &str, sampling_rate: f64, service_name: &str) -> Tracer
 
 #[tokio::main]
 async fn main() {
-    let config = Config::init_from_env().expect("Invalid configuration:");
+    let config: Config = load_config().expect("Invalid configuration:");
 
     // Instantiate tracing outputs:
-    //   - stdout with a level configured by the RUST_LOG envvar (default=ERROR)
+    //   - stdout with a level configured by the RUST_LOG envvar (default=ERROR), formatted as
+    //     text or JSON depending on the LOG_FORMAT envvar
     //   - OpenTelemetry if enabled, for levels INFO and higher
-    let log_layer = tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env());
+    let log_layer = fmt_layer(config.log_format).with_filter(EnvFilter::from_default_env());
     let otel_layer = config
         .otel_url
         .clone()
@@ -79,6 +82,7 @@ async fn main() {
         .with(log_layer)
         .with(otel_layer)
         .init();
+    setup_panic_hook();
 
     // Open the TCP port and start the server
     let listener = tokio::net::TcpListener::bind(config.address)
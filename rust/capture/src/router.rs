@@ -6,6 +6,7 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use common_metrics::require_bearer_token;
 use health::HealthRegistry;
 use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -39,6 +40,7 @@ pub fn router<
     redis: Arc<R>,
     billing: BillingLimiter,
     metrics: bool,
+    metrics_bearer_token: Option<String>,
 ) -> Router {
     let state = State {
         sink: Arc::new(sink),
@@ -106,7 +108,9 @@ pub fn router<
     // does not work well.
     if metrics {
         let recorder_handle = setup_metrics_recorder();
-        router.route("/metrics", get(move || ready(recorder_handle.render())))
+        let metrics_router =
+            Router::new().route("/metrics", get(move || ready(recorder_handle.render())));
+        router.merge(require_bearer_token(metrics_router, metrics_bearer_token))
     } else {
         router
     }
@@ -42,6 +42,7 @@ where
             redis_client,
             billing,
             config.export_prometheus,
+            config.metrics_bearer_token,
         )
     } else {
         let sink_liveness = liveness
@@ -82,6 +83,7 @@ where
             redis_client,
             billing,
             config.export_prometheus,
+            config.metrics_bearer_token,
         )
     };
 
@@ -24,6 +24,7 @@ use tracing::{debug, warn};
 
 use capture::config::{Config, KafkaConfig};
 use capture::server::serve;
+use common_logging::LogFormat;
 
 pub static DEFAULT_CONFIG: Lazy<Config> = Lazy::new(|| Config {
     print_sink: false,
@@ -47,6 +48,8 @@ pub static DEFAULT_CONFIG: Lazy<Config> = Lazy::new(|| Config {
     otel_sampling_rate: 0.0,
     otel_service_name: "capture-testing".to_string(),
     export_prometheus: false,
+    metrics_bearer_token: None,
+    log_format: LogFormat::Text,
 });
 
 static TRACING_INIT: Once = Once::new();
@@ -110,6 +110,7 @@ async fn it_matches_django_capture_behaviour() -> anyhow::Result<()> {
             redis,
             billing,
             false,
+            None,
         );
 
         let client = TestClient::new(app);
@@ -0,0 +1,162 @@
+//! # Config
+//!
+//! Layered configuration loading for services using `envconfig`.
+use std::collections::HashMap;
+use std::{env, fs};
+
+use envconfig::Envconfig;
+use thiserror::Error;
+
+/// Environment variable pointing to an optional TOML config file.
+pub const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+
+/// Enumeration of errors that can occur while loading configuration.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {error}")]
+    ReadFileError { path: String, error: std::io::Error },
+    #[error("failed to parse config file {path}: {error}")]
+    ParseFileError {
+        path: String,
+        error: toml::de::Error,
+    },
+    #[error("config file {path} has a non-scalar value for {key}")]
+    UnsupportedValueError { path: String, key: String },
+    #[error(transparent)]
+    EnvconfigError(#[from] envconfig::Error),
+}
+
+/// Load a configuration from, in increasing order of precedence:
+/// 1. The defaults declared on the `Envconfig` struct.
+/// 2. A flat TOML file, if the `CONFIG_FILE` environment variable points to one.
+/// 3. Environment variables.
+///
+/// Keys in the file use the same names as the environment variables, case-insensitively, so
+/// `database_url = "..."` in the file sets the same field as `DATABASE_URL`.
+pub fn load_config<C: Envconfig>() -> Result<C, ConfigError> {
+    let env_vars: HashMap<String, String> = env::vars().collect();
+    let config_file = env_vars.get(CONFIG_FILE_VAR).cloned();
+
+    load_config_from(config_file.as_deref(), env_vars)
+}
+
+fn load_config_from<C: Envconfig>(
+    config_file: Option<&str>,
+    env_vars: HashMap<String, String>,
+) -> Result<C, ConfigError> {
+    let mut values = match config_file {
+        Some(path) => read_config_file(path)?,
+        None => HashMap::new(),
+    };
+    values.extend(env_vars);
+
+    Ok(C::init_from_hashmap(&values)?)
+}
+
+fn read_config_file(path: &str) -> Result<HashMap<String, String>, ConfigError> {
+    let contents = fs::read_to_string(path).map_err(|error| ConfigError::ReadFileError {
+        path: path.to_owned(),
+        error,
+    })?;
+    let table: toml::Table = contents
+        .parse()
+        .map_err(|error| ConfigError::ParseFileError {
+            path: path.to_owned(),
+            error,
+        })?;
+
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                toml::Value::Boolean(b) => b.to_string(),
+                _ => {
+                    return Err(ConfigError::UnsupportedValueError {
+                        path: path.to_owned(),
+                        key,
+                    })
+                }
+            };
+            Ok((key.to_ascii_uppercase(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Envconfig, Debug)]
+    struct TestConfig {
+        #[envconfig(default = "0.0.0.0")]
+        pub host: String,
+
+        #[envconfig(default = "3300")]
+        pub port: u16,
+
+        #[envconfig(default = "false")]
+        pub enabled: bool,
+    }
+
+    /// A config file in the temporary directory, removed when dropped.
+    struct TempConfigFile(String);
+
+    impl TempConfigFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = env::temp_dir().join(format!(
+                "common-config-{}-{}.toml",
+                name,
+                std::process::id()
+            ));
+            fs::write(&path, contents).expect("failed to write config file");
+            Self(path.to_str().unwrap().to_owned())
+        }
+    }
+
+    impl Drop for TempConfigFile {
+        fn drop(&mut self) {
+            fs::remove_file(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn test_defaults_without_file() {
+        let config: TestConfig = load_config_from(None, HashMap::new()).unwrap();
+
+        assert_eq!(config.host, "0.0.0.0");
+        assert_eq!(config.port, 3300);
+        assert!(!config.enabled);
+    }
+
+    #[test]
+    fn test_file_values_are_overridden_by_env_vars() {
+        let file = TempConfigFile::new(
+            "override",
+            "host = \"localhost\"\nPORT = 8000\nenabled = true\n",
+        );
+        let env_vars = HashMap::from([("PORT".to_owned(), "9000".to_owned())]);
+
+        let config: TestConfig = load_config_from(Some(&file.0), env_vars).unwrap();
+
+        assert_eq!(config.host, "localhost");
+        assert_eq!(config.port, 9000);
+        assert!(config.enabled);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        let missing =
+            load_config_from::<TestConfig>(Some("/nonexistent/config.toml"), HashMap::new());
+        assert!(matches!(missing, Err(ConfigError::ReadFileError { .. })));
+
+        let file = TempConfigFile::new("nested", "[server]\nhost = \"localhost\"\n");
+        let nested = load_config_from::<TestConfig>(Some(&file.0), HashMap::new());
+        assert!(matches!(
+            nested,
+            Err(ConfigError::UnsupportedValueError { key, .. }) if key == "server"
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! # Logging
+//!
+//! Log output shared by the services, selectable between human-readable text and JSON.
+use std::str::FromStr;
+
+use thiserror::Error;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Format of the log lines written to stdout, configured with `LOG_FORMAT=text|json`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines, for local development.
+    #[default]
+    Text,
+    /// One JSON object per line, with event and span fields as keys, for log ingestion.
+    Json,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("{0} is not a valid log format, expected text or json")]
+pub struct ParseLogFormatError(pub String);
+
+/// Allow casting `LogFormat` from strings, so it can be used in `envconfig` structs.
+impl FromStr for LogFormat {
+    type Err = ParseLogFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(ParseLogFormatError(s.to_owned())),
+        }
+    }
+}
+
+/// Install a global subscriber logging to stdout in the given format, at the level configured by
+/// the `RUST_LOG` envvar (default=ERROR), like `tracing_subscriber::fmt::init` does.
+/// Panics if a global subscriber was already installed.
+pub fn init(format: LogFormat) {
+    tracing_subscriber::registry()
+        .with(fmt_layer(format).with_filter(EnvFilter::from_default_env()))
+        .init();
+}
+
+/// Build the layer writing logs to stdout in the given format. Filtering is left to the caller.
+pub fn fmt_layer<S>(format: LogFormat) -> Box<dyn Layer<S> + Send + Sync + 'static>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fmt_layer_with_writer(format, std::io::stdout)
+}
+
+fn fmt_layer_with_writer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// A `MakeWriter` capturing everything written to it.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn capture_logs(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        let subscriber =
+            tracing_subscriber::registry().with(fmt_layer_with_writer(format, buffer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", path = "/flags");
+            let _entered = span.enter();
+            tracing::info!(team_id = 1, "evaluated flags");
+        });
+
+        let logs = buffer.0.lock().unwrap().clone();
+        String::from_utf8(logs).unwrap()
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::from_str("text"), Ok(LogFormat::Text));
+        assert_eq!(LogFormat::from_str("JSON"), Ok(LogFormat::Json));
+        assert_eq!(
+            LogFormat::from_str("logfmt"),
+            Err(ParseLogFormatError("logfmt".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_json_logs_are_parseable() {
+        let logs = capture_logs(LogFormat::Json);
+        let lines: Vec<&str> = logs.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let line: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "evaluated flags");
+        assert_eq!(line["fields"]["team_id"], 1);
+        assert_eq!(line["span"]["path"], "/flags");
+    }
+
+    #[test]
+    fn test_text_logs_are_not_json() {
+        let logs = capture_logs(LogFormat::Text);
+
+        assert!(logs.contains("evaluated flags"));
+        assert!(serde_json::from_str::<serde_json::Value>(logs.trim()).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! # Metrics
+//!
+//! Metrics shared by the services that export a Prometheus endpoint, independently of how each
+//! one sets it up, and helpers to protect the routes they are scraped from.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+
+/// Install a panic hook that increments `service_panics_total` before delegating to the
+/// previously installed hook, so that panics in tokio tasks and worker threads show up on
+/// dashboards even when they don't bring the process down. Should be called once, at startup.
+pub fn setup_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        record_panic();
+        previous_hook(info);
+    }));
+}
+
+/// Count a panic, labeled by a coarse category of the panicking thread to keep cardinality low.
+fn record_panic() {
+    let thread = match std::thread::current().name() {
+        Some("main") => "main",
+        Some(name) if name.starts_with("tokio-runtime-worker") => "tokio_worker",
+        Some(_) => "other",
+        None => "unnamed",
+    };
+
+    metrics::counter!("service_panics_total", "thread" => thread).increment(1);
+}
+
+/// Require an `Authorization: Bearer <token>` header on every route already added to `router`,
+/// if `bearer_token` is set to a non-empty token. An empty token leaves the routes open, as
+/// `Bearer ` alone would be trivial to guess. Routes added to the returned router afterwards are
+/// left open, so this should be called on a router holding only the metrics route.
+pub fn require_bearer_token(router: Router, bearer_token: Option<String>) -> Router {
+    match bearer_token.filter(|token| !token.is_empty()) {
+        Some(token) => router.route_layer(axum::middleware::from_fn_with_state(
+            Arc::new(format!("Bearer {}", token)),
+            check_authorization,
+        )),
+        None => router,
+    }
+}
+
+/// Middleware rejecting requests that don't carry the expected `Authorization` header.
+async fn check_authorization(
+    State(expected): State<Arc<String>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .is_some_and(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()));
+
+    if authorized {
+        next.run(req).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+/// Compare two byte strings without short-circuiting on the first difference, so that response
+/// timing doesn't reveal how much of a guessed token is correct. Only the length can leak.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[test]
+    fn test_panic_hook_counts_panics() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        // The panic hook is process-wide: restore the original one once done so that other
+        // tests in this binary are unaffected.
+        let original_hook = std::panic::take_hook();
+        setup_panic_hook();
+        metrics::with_local_recorder(&recorder, || {
+            let result = std::panic::catch_unwind(|| panic!("expected panic"));
+            assert!(result.is_err());
+        });
+        drop(std::panic::take_hook());
+        std::panic::set_hook(original_hook);
+
+        // Test threads are named after the test, so they fall in the "other" category.
+        assert!(handle
+            .render()
+            .contains(r#"service_panics_total{thread="other"} 1"#));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"Bearer secret", b"Bearer secret"));
+        assert!(!constant_time_eq(b"Bearer secreT", b"Bearer secret"));
+        assert!(!constant_time_eq(b"Bearer secre", b"Bearer secret"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
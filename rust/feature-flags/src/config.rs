@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 
+use common_logging::LogFormat;
 use envconfig::Envconfig;
 
 #[derive(Envconfig, Clone)]
@@ -21,4 +22,7 @@ pub struct Config {
 
     #[envconfig(default = "redis://localhost:6379/")]
     pub redis_url: String,
+
+    #[envconfig(default = "text")]
+    pub log_format: LogFormat,
 }
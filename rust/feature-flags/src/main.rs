@@ -1,8 +1,5 @@
-use envconfig::Envconfig;
+use common_config::load_config;
 use tokio::signal;
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{EnvFilter, Layer};
 
 use feature_flags::config::Config;
 use feature_flags::server::serve;
@@ -24,12 +21,12 @@ async fn shutdown() {
 
 #[tokio::main]
 async fn main() {
-    let config = Config::init_from_env().expect("Invalid configuration:");
+    let config: Config = load_config().expect("Invalid configuration:");
 
     // Basic logging for now:
-    //   - stdout with a level configured by the RUST_LOG envvar (default=ERROR)
-    let log_layer = tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env());
-    tracing_subscriber::registry().with(log_layer).init();
+    //   - stdout with a level configured by the RUST_LOG envvar (default=ERROR), formatted as
+    //     text or JSON depending on the LOG_FORMAT envvar
+    common_logging::init(config.log_format);
 
     // Open the TCP port and start the server
     let listener = tokio::net::TcpListener::bind(config.address)
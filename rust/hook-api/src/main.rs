@@ -1,9 +1,10 @@
 use axum::Router;
 use config::Config;
-use envconfig::Envconfig;
 use eyre::Result;
 
-use hook_common::metrics::setup_metrics_routes;
+use common_config::load_config;
+use common_metrics::setup_panic_hook;
+use hook_common::metrics::{setup_metrics_routes_with_options, MetricsOptions};
 use hook_common::pgqueue::PgQueue;
 
 mod config;
@@ -19,9 +20,10 @@ async fn listen(app: Router, bind: String) -> Result<()> {
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    let config: Config = load_config().expect("failed to load configuration");
 
-    let config = Config::init_from_env().expect("failed to load configuration from env");
+    common_logging::init(config.log_format);
+    setup_panic_hook();
 
     let pg_queue = PgQueue::new(
         // TODO: Coupling the queue name to the PgQueue object doesn't seem ideal from the api
@@ -35,7 +37,13 @@ async fn main() {
     .expect("failed to initialize queue");
 
     let app = handlers::add_routes(Router::new(), pg_queue, config.max_body_size);
-    let app = setup_metrics_routes(app);
+    let app = setup_metrics_routes_with_options(
+        app,
+        MetricsOptions {
+            bearer_token: config.metrics_bearer_token.clone(),
+            ..Default::default()
+        },
+    );
 
     match listen(app, config.bind()).await {
         Ok(_) => {}
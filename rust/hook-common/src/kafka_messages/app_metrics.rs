@@ -1,10 +1,16 @@
-use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use tracing::{error, warn};
 use uuid::Uuid;
 
 use super::{deserialize_datetime, serialize_datetime};
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum AppMetricCategory {
     ProcessEvent,
     OnEvent,
@@ -13,15 +19,75 @@ pub enum AppMetricCategory {
     ComposeWebhook,
 }
 
+#[derive(Error, Debug, PartialEq)]
+#[error("{0} is not a valid AppMetricCategory")]
+pub struct ParseAppMetricCategoryError(pub String);
+
+impl AppMetricCategory {
+    /// Every `AppMetricCategory` variant, used when parsing from a string.
+    pub const ALL: [AppMetricCategory; 5] = [
+        AppMetricCategory::ProcessEvent,
+        AppMetricCategory::OnEvent,
+        AppMetricCategory::ScheduledTask,
+        AppMetricCategory::Webhook,
+        AppMetricCategory::ComposeWebhook,
+    ];
+
+    /// The string representation of every variant in `ALL`, listed in deserialization errors.
+    const NAMES: [&'static str; Self::ALL.len()] = {
+        let mut names = [""; Self::ALL.len()];
+        let mut i = 0;
+        while i < names.len() {
+            names[i] = Self::ALL[i].as_str();
+            i += 1;
+        }
+        names
+    };
+
+    /// The string representation shared by `Display`, `FromStr` and serde so that they can't
+    /// drift apart. These are consumed by the plugin-server and frontend.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            AppMetricCategory::ProcessEvent => "processEvent",
+            AppMetricCategory::OnEvent => "onEvent",
+            AppMetricCategory::ScheduledTask => "scheduledTask",
+            AppMetricCategory::Webhook => "webhook",
+            AppMetricCategory::ComposeWebhook => "composeWebhook",
+        }
+    }
+}
+
+/// Allow casting `AppMetricCategory` from strings.
+impl FromStr for AppMetricCategory {
+    type Err = ParseAppMetricCategoryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AppMetricCategory::ALL
+            .into_iter()
+            .find(|category| category.as_str() == s)
+            .ok_or_else(|| ParseAppMetricCategoryError(s.to_owned()))
+    }
+}
+
+/// Implement `std::fmt::Display` to convert AppMetricCategory to string.
+impl fmt::Display for AppMetricCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 // NOTE: These are stored in Postgres and deserialized by the cleanup/janitor process, so these
 // names need to remain stable, or new variants need to be deployed to the cleanup/janitor
 // process before they are used.
-#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ErrorType {
     TimeoutError,
     ConnectionError,
     BadHttpStatus(u16),
     ParseError,
+    TlsError,
+    DnsError,
+    RateLimited,
 }
 
 // NOTE: This is stored in Postgres and deserialized by the cleanup/janitor process, so this
@@ -75,18 +141,244 @@ pub struct AppMetric {
     pub error_details: Option<ErrorDetails>,
 }
 
+/// Enumeration of reasons an `AppMetric` may be rejected by `AppMetric::validate`.
+#[derive(Error, Debug, PartialEq)]
+pub enum AppMetricValidationError {
+    #[error("plugin_config_id must not be negative, got {0}")]
+    NegativePluginConfigId(i32),
+    #[error("job_id must not be empty")]
+    EmptyJobId,
+    #[error("{0} is not a valid HTTP status code")]
+    InvalidHttpStatus(u16),
+}
+
+impl AppMetric {
+    /// Initialize an `AppMetricBuilder` with the fields that have no sensible default.
+    pub fn build(
+        timestamp: DateTime<Utc>,
+        team_id: u32,
+        plugin_config_id: i32,
+        category: AppMetricCategory,
+    ) -> AppMetricBuilder {
+        AppMetricBuilder::new(timestamp, team_id, plugin_config_id, category)
+    }
+
+    /// Serialize this metric to JSON for producing. If serialization fails, the metric is
+    /// dead-lettered instead of failing the whole batch: it is retried without
+    /// `error_details`, and dropped if that still fails. Both outcomes are logged and counted.
+    pub fn to_json_or_dead_letter(&self) -> Option<String> {
+        self.to_json_or_dead_letter_with(serde_json::to_string)
+    }
+
+    fn to_json_or_dead_letter_with<F>(&self, serialize: F) -> Option<String>
+    where
+        F: Fn(&AppMetric) -> Result<String, serde_json::Error>,
+    {
+        let error = match serialize(self) {
+            Ok(payload) => return Some(payload),
+            Err(error) => error,
+        };
+
+        if self.error_details.is_some() {
+            let simplified = AppMetric {
+                error_details: None,
+                ..self.clone()
+            };
+
+            if let Ok(payload) = serialize(&simplified) {
+                warn!(
+                    "dropped error_details from app metric that failed to serialize: {}",
+                    error
+                );
+                metrics::counter!("app_metrics_dead_lettered", "outcome" => "error_details_dropped")
+                    .increment(1);
+                return Some(payload);
+            }
+        }
+
+        error!("dropping app metric that failed to serialize: {}", error);
+        metrics::counter!("app_metrics_dead_lettered", "outcome" => "dropped").increment(1);
+        None
+    }
+
+    /// Check that fields hold sensible values. Producers should call this before serializing, as
+    /// invalid metrics would otherwise end up stored downstream.
+    pub fn validate(&self) -> Result<(), AppMetricValidationError> {
+        if self.plugin_config_id < 0 {
+            return Err(AppMetricValidationError::NegativePluginConfigId(
+                self.plugin_config_id,
+            ));
+        }
+
+        if matches!(&self.job_id, Some(job_id) if job_id.is_empty()) {
+            return Err(AppMetricValidationError::EmptyJobId);
+        }
+
+        if let Some(ErrorType::BadHttpStatus(status)) = self.error_type {
+            if !(100..=599).contains(&status) {
+                return Err(AppMetricValidationError::InvalidHttpStatus(status));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The key to produce this metric to Kafka with. The key determines partitioning, so all
+    /// metrics for the same team, plugin config and job land on the same partition and can be
+    /// aggregated in order.
+    pub fn kafka_key(&self) -> String {
+        match &self.job_id {
+            Some(job_id) => format!("{}:{}:{}", self.team_id, self.plugin_config_id, job_id),
+            None => format!("{}:{}", self.team_id, self.plugin_config_id),
+        }
+    }
+}
+
+/// Builder pattern struct to provide an `AppMetric`.
+/// Counters default to 0 and all optional fields default to `None`.
+pub struct AppMetricBuilder {
+    app_metric: AppMetric,
+}
+
+impl AppMetricBuilder {
+    pub fn new(
+        timestamp: DateTime<Utc>,
+        team_id: u32,
+        plugin_config_id: i32,
+        category: AppMetricCategory,
+    ) -> Self {
+        Self {
+            app_metric: AppMetric {
+                timestamp,
+                team_id,
+                plugin_config_id,
+                job_id: None,
+                category,
+                successes: 0,
+                successes_on_retry: 0,
+                failures: 0,
+                error_uuid: None,
+                error_type: None,
+                error_details: None,
+            },
+        }
+    }
+
+    pub fn job_id(mut self, job_id: &str) -> AppMetricBuilder {
+        self.app_metric.job_id = Some(job_id.to_owned());
+        self
+    }
+
+    pub fn successes(mut self, successes: u32) -> AppMetricBuilder {
+        self.app_metric.successes = successes;
+        self
+    }
+
+    pub fn successes_on_retry(mut self, successes_on_retry: u32) -> AppMetricBuilder {
+        self.app_metric.successes_on_retry = successes_on_retry;
+        self
+    }
+
+    pub fn failures(mut self, failures: u32) -> AppMetricBuilder {
+        self.app_metric.failures = failures;
+        self
+    }
+
+    pub fn error_uuid(mut self, error_uuid: Uuid) -> AppMetricBuilder {
+        self.app_metric.error_uuid = Some(error_uuid);
+        self
+    }
+
+    pub fn error_type(mut self, error_type: ErrorType) -> AppMetricBuilder {
+        self.app_metric.error_type = Some(error_type);
+        self
+    }
+
+    pub fn error_details(mut self, error_details: ErrorDetails) -> AppMetricBuilder {
+        self.app_metric.error_details = Some(error_details);
+        self
+    }
+
+    /// Provide a validated `AppMetric` according to build parameters provided thus far.
+    pub fn provide(&self) -> Result<AppMetric, AppMetricValidationError> {
+        self.app_metric.validate()?;
+        Ok(self.app_metric.clone())
+    }
+}
+
+/// The fields `AppMetricAggregator` groups by: metrics that match on all of these are summed.
+type AggregationKey = (
+    DateTime<Utc>,
+    u32,
+    i32,
+    AppMetricCategory,
+    Option<ErrorType>,
+);
+
+/// Pre-aggregates `AppMetric`s in memory so fewer messages need to be produced to Kafka.
+/// Metrics are grouped by timestamp bucket, team, plugin config, category and error type, and
+/// their counters are summed. All other fields are taken from the first metric seen in a group.
+pub struct AppMetricAggregator {
+    bucket: Duration,
+    indices: HashMap<AggregationKey, usize>,
+    aggregated: Vec<AppMetric>,
+}
+
+impl AppMetricAggregator {
+    /// Create an aggregator that truncates timestamps to multiples of `bucket`.
+    pub fn new(bucket: Duration) -> Self {
+        Self {
+            bucket,
+            indices: HashMap::new(),
+            aggregated: Vec::new(),
+        }
+    }
+
+    /// Add an `AppMetric`, summing it into an existing group if one matches.
+    pub fn add(&mut self, mut app_metric: AppMetric) {
+        // Truncation can only fail for out of range durations, in which case we don't bucket.
+        app_metric.timestamp = app_metric
+            .timestamp
+            .duration_trunc(self.bucket)
+            .unwrap_or(app_metric.timestamp);
+
+        let key = (
+            app_metric.timestamp,
+            app_metric.team_id,
+            app_metric.plugin_config_id,
+            app_metric.category.clone(),
+            app_metric.error_type.clone(),
+        );
+
+        match self.indices.get(&key) {
+            Some(&index) => {
+                let existing = &mut self.aggregated[index];
+                existing.successes = existing.successes.saturating_add(app_metric.successes);
+                existing.successes_on_retry = existing
+                    .successes_on_retry
+                    .saturating_add(app_metric.successes_on_retry);
+                existing.failures = existing.failures.saturating_add(app_metric.failures);
+            }
+            None => {
+                self.indices.insert(key, self.aggregated.len());
+                self.aggregated.push(app_metric);
+            }
+        }
+    }
+
+    /// Return the rolled-up `AppMetric`s in the order their groups were first seen, and reset
+    /// the aggregator.
+    pub fn flush(&mut self) -> Vec<AppMetric> {
+        self.indices.clear();
+        std::mem::take(&mut self.aggregated)
+    }
+}
+
 fn serialize_category<S>(category: &AppMetricCategory, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let category_str = match category {
-        AppMetricCategory::ProcessEvent => "processEvent",
-        AppMetricCategory::OnEvent => "onEvent",
-        AppMetricCategory::ScheduledTask => "scheduledTask",
-        AppMetricCategory::Webhook => "webhook",
-        AppMetricCategory::ComposeWebhook => "composeWebhook",
-    };
-    serializer.serialize_str(category_str)
+    serializer.serialize_str(category.as_str())
 }
 
 fn deserialize_category<'de, D>(deserializer: D) -> Result<AppMetricCategory, D::Error>
@@ -95,27 +387,8 @@ where
 {
     let s: String = Deserialize::deserialize(deserializer)?;
 
-    let category = match &s[..] {
-        "processEvent" => AppMetricCategory::ProcessEvent,
-        "onEvent" => AppMetricCategory::OnEvent,
-        "scheduledTask" => AppMetricCategory::ScheduledTask,
-        "webhook" => AppMetricCategory::Webhook,
-        "composeWebhook" => AppMetricCategory::ComposeWebhook,
-        _ => {
-            return Err(serde::de::Error::unknown_variant(
-                &s,
-                &[
-                    "processEvent",
-                    "onEvent",
-                    "scheduledTask",
-                    "webhook",
-                    "composeWebhook",
-                ],
-            ))
-        }
-    };
-
-    Ok(category)
+    AppMetricCategory::from_str(&s)
+        .map_err(|_| serde::de::Error::unknown_variant(&s, &AppMetricCategory::NAMES))
 }
 
 fn serialize_error_type<S>(error_type: &Option<ErrorType>, serializer: S) -> Result<S::Ok, S::Error>
@@ -132,6 +405,9 @@ where
         ErrorType::TimeoutError => "Timeout Error".to_owned(),
         ErrorType::BadHttpStatus(s) => format!("Bad HTTP Status: {}", s),
         ErrorType::ParseError => "Parse Error".to_owned(),
+        ErrorType::TlsError => "SSL Error".to_owned(),
+        ErrorType::DnsError => "DNS Error".to_owned(),
+        ErrorType::RateLimited => "Rate Limited".to_owned(),
     };
     serializer.serialize_str(&error_type)
 }
@@ -147,10 +423,13 @@ where
                 "Connection Error" => ErrorType::ConnectionError,
                 "Timeout Error" => ErrorType::TimeoutError,
                 _ if s.starts_with("Bad HTTP Status:") => {
-                    let status = &s["Bad HTTP Status:".len()..];
+                    let status = s["Bad HTTP Status:".len()..].trim();
                     ErrorType::BadHttpStatus(status.parse().map_err(serde::de::Error::custom)?)
                 }
                 "Parse Error" => ErrorType::ParseError,
+                "SSL Error" => ErrorType::TlsError,
+                "DNS Error" => ErrorType::DnsError,
+                "Rate Limited" => ErrorType::RateLimited,
                 _ => {
                     return Err(serde::de::Error::unknown_variant(
                         &s,
@@ -159,6 +438,9 @@ where
                             "Timeout Error",
                             "Bad HTTP Status: <status>",
                             "Parse Error",
+                            "SSL Error",
+                            "DNS Error",
+                            "Rate Limited",
                         ],
                     ))
                 }
@@ -205,4 +487,376 @@ mod tests {
 
         assert_eq!(serialized_json, expected_json);
     }
+
+    fn app_metric_with_error_type(error_type: Option<ErrorType>) -> AppMetric {
+        use chrono::prelude::*;
+
+        AppMetric {
+            timestamp: Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap(),
+            team_id: 123,
+            plugin_config_id: 456,
+            job_id: None,
+            category: AppMetricCategory::Webhook,
+            successes: 0,
+            successes_on_retry: 0,
+            failures: 1,
+            error_uuid: None,
+            error_type,
+            error_details: None,
+        }
+    }
+
+    #[test]
+    fn test_error_type_round_trip() {
+        for (error_type, expected) in [
+            (ErrorType::TlsError, "SSL Error"),
+            (ErrorType::DnsError, "DNS Error"),
+            (ErrorType::RateLimited, "Rate Limited"),
+        ] {
+            let app_metric = app_metric_with_error_type(Some(error_type));
+
+            let serialized_json = serde_json::to_string(&app_metric).unwrap();
+            assert!(serialized_json.contains(&format!(r#""error_type":"{}""#, expected)));
+
+            let deserialized: AppMetric = serde_json::from_str(&serialized_json).unwrap();
+            assert_eq!(deserialized, app_metric);
+        }
+    }
+
+    #[test]
+    fn test_legacy_error_types_deserialize() {
+        for (serialized, expected) in [
+            ("Connection Error", ErrorType::ConnectionError),
+            ("Timeout Error", ErrorType::TimeoutError),
+            ("Bad HTTP Status: 500", ErrorType::BadHttpStatus(500)),
+            ("Parse Error", ErrorType::ParseError),
+        ] {
+            let json = format!(
+                r#"{{"timestamp":"2023-12-14 12:02:00","team_id":123,"plugin_config_id":456,"category":"webhook","successes":0,"successes_on_retry":0,"failures":1,"error_type":"{}"}}"#,
+                serialized
+            );
+
+            let deserialized: AppMetric = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, app_metric_with_error_type(Some(expected)));
+        }
+    }
+
+    #[test]
+    fn test_new_error_types_round_trip_through_job_error_storage() {
+        // The janitor reads `ErrorType` back out of the Postgres `last_error` column, which uses
+        // the derived serde representation rather than the Kafka string mapping.
+        for error_type in [
+            ErrorType::TlsError,
+            ErrorType::DnsError,
+            ErrorType::RateLimited,
+        ] {
+            let stored = serde_json::to_value(&error_type).unwrap();
+            let restored: ErrorType = serde_json::from_value(stored).unwrap();
+            assert_eq!(restored, error_type);
+        }
+    }
+
+    #[test]
+    fn test_aggregator_sums_metrics_in_the_same_bucket() {
+        use chrono::prelude::*;
+
+        let mut aggregator = AppMetricAggregator::new(Duration::minutes(1));
+
+        let mut first = app_metric_with_error_type(Some(ErrorType::TimeoutError));
+        first.timestamp = Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 5).unwrap();
+        first.error_details = Some(ErrorDetails {
+            error: Error {
+                name: "FirstError".to_owned(),
+                message: None,
+                stack: None,
+            },
+        });
+        let mut second = app_metric_with_error_type(Some(ErrorType::TimeoutError));
+        second.timestamp = Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 45).unwrap();
+        second.successes = 3;
+        second.successes_on_retry = 1;
+        second.failures = 2;
+        second.error_details = Some(ErrorDetails {
+            error: Error {
+                name: "SecondError".to_owned(),
+                message: None,
+                stack: None,
+            },
+        });
+
+        aggregator.add(first.clone());
+        aggregator.add(second);
+
+        let aggregated = aggregator.flush();
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(
+            aggregated[0].timestamp,
+            Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap()
+        );
+        assert_eq!(aggregated[0].successes, 3);
+        assert_eq!(aggregated[0].successes_on_retry, 1);
+        assert_eq!(aggregated[0].failures, 3);
+        assert_eq!(aggregated[0].error_details, first.error_details);
+
+        assert!(aggregator.flush().is_empty());
+    }
+
+    #[test]
+    fn test_aggregator_keeps_groups_separate() {
+        use chrono::prelude::*;
+
+        let mut aggregator = AppMetricAggregator::new(Duration::minutes(1));
+
+        let first = app_metric_with_error_type(None);
+        let mut other_bucket = first.clone();
+        other_bucket.timestamp = Utc.with_ymd_and_hms(2023, 12, 14, 12, 3, 0).unwrap();
+        let mut other_team = first.clone();
+        other_team.team_id = 1;
+        let mut other_error_type = first.clone();
+        other_error_type.error_type = Some(ErrorType::ConnectionError);
+
+        aggregator.add(first.clone());
+        aggregator.add(other_bucket.clone());
+        aggregator.add(other_team.clone());
+        aggregator.add(other_error_type.clone());
+
+        assert_eq!(
+            aggregator.flush(),
+            vec![first, other_bucket, other_team, other_error_type]
+        );
+    }
+
+    #[test]
+    fn test_all_app_metric_categories_are_listed() {
+        // The derived `Deserialize` lists every variant when rejecting an unknown one, so this
+        // fails if a variant is added to the enum but not to `AppMetricCategory::ALL`.
+        let error = serde_json::from_value::<AppMetricCategory>(serde_json::json!("NotACategory"))
+            .unwrap_err();
+        let listed = AppMetricCategory::ALL
+            .iter()
+            .map(|category| format!("`{:?}`", category))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        assert_eq!(
+            error.to_string(),
+            format!("unknown variant `NotACategory`, expected one of {}", listed)
+        );
+    }
+
+    #[test]
+    fn test_unknown_app_metric_category_lists_expected_names() {
+        let error = deserialize_category(serde_json::json!("notACategory")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown variant `notACategory`, expected one of `processEvent`, `onEvent`, `scheduledTask`, `webhook`, `composeWebhook`"
+        );
+    }
+
+    #[test]
+    fn test_app_metric_category_string_round_trip() {
+        let names = [
+            "processEvent",
+            "onEvent",
+            "scheduledTask",
+            "webhook",
+            "composeWebhook",
+        ];
+
+        for (category, name) in AppMetricCategory::ALL.into_iter().zip(names) {
+            assert_eq!(category.to_string(), name);
+            assert_eq!(
+                AppMetricCategory::from_str(&category.to_string()),
+                Ok(category.clone())
+            );
+
+            let mut app_metric = app_metric_with_error_type(None);
+            app_metric.category = category;
+            let serialized = serde_json::to_value(&app_metric).unwrap();
+            assert_eq!(serialized["category"], name);
+        }
+
+        assert_eq!(
+            AppMetricCategory::from_str("unknownCategory"),
+            Err(ParseAppMetricCategoryError("unknownCategory".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_kafka_key() {
+        let app_metric = app_metric_with_error_type(None);
+        let mut same_plugin = app_metric_with_error_type(Some(ErrorType::TimeoutError));
+        same_plugin.successes = 5;
+        let mut other_plugin = app_metric.clone();
+        other_plugin.plugin_config_id = 789;
+        let mut other_team = app_metric.clone();
+        other_team.team_id = 1;
+        let mut with_job = app_metric.clone();
+        with_job.job_id = Some("job".to_owned());
+
+        assert_eq!(app_metric.kafka_key(), "123:456");
+        assert_eq!(app_metric.kafka_key(), same_plugin.kafka_key());
+        assert_ne!(app_metric.kafka_key(), other_plugin.kafka_key());
+        assert_ne!(app_metric.kafka_key(), other_team.kafka_key());
+        assert_eq!(with_job.kafka_key(), "123:456:job");
+    }
+
+    #[test]
+    fn test_validate() {
+        let app_metric = app_metric_with_error_type(Some(ErrorType::BadHttpStatus(503)));
+        assert_eq!(app_metric.validate(), Ok(()));
+
+        let mut negative_plugin_config_id = app_metric.clone();
+        negative_plugin_config_id.plugin_config_id = -1;
+        assert_eq!(
+            negative_plugin_config_id.validate(),
+            Err(AppMetricValidationError::NegativePluginConfigId(-1))
+        );
+
+        let mut empty_job_id = app_metric.clone();
+        empty_job_id.job_id = Some("".to_owned());
+        assert_eq!(
+            empty_job_id.validate(),
+            Err(AppMetricValidationError::EmptyJobId)
+        );
+
+        for status in [0, 99, 600] {
+            let invalid_status = app_metric_with_error_type(Some(ErrorType::BadHttpStatus(status)));
+            assert_eq!(
+                invalid_status.validate(),
+                Err(AppMetricValidationError::InvalidHttpStatus(status))
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        use chrono::prelude::*;
+
+        let timestamp = Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap();
+        let app_metric = AppMetric::build(timestamp, 123, 456, AppMetricCategory::Webhook)
+            .provide()
+            .unwrap();
+
+        assert_eq!(
+            app_metric,
+            AppMetric {
+                timestamp,
+                team_id: 123,
+                plugin_config_id: 456,
+                job_id: None,
+                category: AppMetricCategory::Webhook,
+                successes: 0,
+                successes_on_retry: 0,
+                failures: 0,
+                error_uuid: None,
+                error_type: None,
+                error_details: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_builder_setters_and_validation() {
+        use chrono::prelude::*;
+
+        let timestamp = Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap();
+        let app_metric = AppMetric::build(timestamp, 123, 456, AppMetricCategory::Webhook)
+            .failures(1)
+            .error_type(ErrorType::TimeoutError)
+            .provide()
+            .unwrap();
+        assert_eq!(
+            app_metric,
+            app_metric_with_error_type(Some(ErrorType::TimeoutError))
+        );
+
+        let invalid = AppMetric::build(timestamp, 123, -1, AppMetricCategory::Webhook).provide();
+        assert_eq!(
+            invalid,
+            Err(AppMetricValidationError::NegativePluginConfigId(-1))
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_can_be_represented_either_way() {
+        // Historical rows store 429s as `BadHttpStatus(429)`, newer ones as `RateLimited`.
+        for error_type in [ErrorType::BadHttpStatus(429), ErrorType::RateLimited] {
+            let app_metric = app_metric_with_error_type(Some(error_type));
+
+            let serialized_json = serde_json::to_string(&app_metric).unwrap();
+            let deserialized: AppMetric = serde_json::from_str(&serialized_json).unwrap();
+            assert_eq!(deserialized, app_metric);
+            assert_eq!(deserialized.validate(), Ok(()));
+        }
+    }
+
+    fn failing_serializer(
+        fail_on_error_details: bool,
+    ) -> impl Fn(&AppMetric) -> Result<String, serde_json::Error> {
+        move |metric| {
+            if !fail_on_error_details || metric.error_details.is_some() {
+                Err(serde::ser::Error::custom("unserializable"))
+            } else {
+                serde_json::to_string(metric)
+            }
+        }
+    }
+
+    fn render_dead_letter_counters<F>(app_metric: &AppMetric, f: F) -> (Option<String>, String)
+    where
+        F: Fn(&AppMetric) -> Result<String, serde_json::Error>,
+    {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        let payload =
+            metrics::with_local_recorder(&recorder, || app_metric.to_json_or_dead_letter_with(f));
+
+        (payload, handle.render())
+    }
+
+    #[test]
+    fn test_dead_letter_drops_unserializable_error_details() {
+        let mut app_metric = app_metric_with_error_type(Some(ErrorType::ConnectionError));
+        app_metric.error_details = Some(ErrorDetails {
+            error: Error {
+                name: "FooError".to_owned(),
+                message: None,
+                stack: None,
+            },
+        });
+
+        let (payload, rendered) =
+            render_dead_letter_counters(&app_metric, failing_serializer(true));
+
+        let deserialized: AppMetric = serde_json::from_str(&payload.unwrap()).unwrap();
+        assert_eq!(deserialized.error_details, None);
+        assert_eq!(deserialized.error_type, app_metric.error_type);
+        assert!(
+            rendered.contains(r#"app_metrics_dead_lettered{outcome="error_details_dropped"} 1"#)
+        );
+    }
+
+    #[test]
+    fn test_dead_letter_drops_unserializable_metric() {
+        let app_metric = app_metric_with_error_type(None);
+
+        let (payload, rendered) =
+            render_dead_letter_counters(&app_metric, failing_serializer(false));
+
+        assert_eq!(payload, None);
+        assert!(rendered.contains(r#"app_metrics_dead_lettered{outcome="dropped"} 1"#));
+    }
+
+    #[test]
+    fn test_serializable_metric_is_not_dead_lettered() {
+        let app_metric = app_metric_with_error_type(None);
+
+        let (payload, rendered) = render_dead_letter_counters(&app_metric, serde_json::to_string);
+
+        assert_eq!(payload, Some(serde_json::to_string(&app_metric).unwrap()));
+        assert!(!rendered.contains("app_metrics_dead_lettered"));
+    }
 }
@@ -11,15 +11,72 @@ where
     serializer.serialize_str(&datetime.format("%Y-%m-%d %H:%M:%S").to_string())
 }
 
+/// Deserialize a datetime in the format written by `serialize_datetime`. RFC3339 is accepted as a
+/// fallback so that a producer emitting a different format doesn't cause metrics to be dropped.
 pub fn deserialize_datetime<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let formatted: String = Deserialize::deserialize(deserializer)?;
-    let datetime = match NaiveDateTime::parse_from_str(&formatted, "%Y-%m-%d %H:%M:%S") {
-        Ok(d) => d.and_utc(),
-        Err(_) => return Err(serde::de::Error::custom("Invalid datetime format")),
-    };
 
-    Ok(datetime)
+    if let Ok(d) = NaiveDateTime::parse_from_str(&formatted, "%Y-%m-%d %H:%M:%S") {
+        return Ok(d.and_utc());
+    }
+
+    match DateTime::parse_from_rfc3339(&formatted) {
+        Ok(d) => Ok(d.with_timezone(&Utc)),
+        Err(_) => Err(serde::de::Error::custom(format!(
+            "Invalid datetime format: expected \"%Y-%m-%d %H:%M:%S\" or RFC3339, got \"{}\"",
+            formatted
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    struct Timestamped {
+        #[serde(deserialize_with = "deserialize_datetime")]
+        timestamp: DateTime<Utc>,
+    }
+
+    #[test]
+    fn test_deserialize_datetime_accepts_serialized_format() {
+        let parsed: Timestamped =
+            serde_json::from_str(r#"{"timestamp":"2023-12-14 12:02:00"}"#).unwrap();
+
+        assert_eq!(
+            parsed.timestamp,
+            Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_datetime_accepts_rfc3339() {
+        let parsed: Timestamped =
+            serde_json::from_str(r#"{"timestamp":"2023-12-14T12:02:00Z"}"#).unwrap();
+        assert_eq!(
+            parsed.timestamp,
+            Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap()
+        );
+
+        let parsed: Timestamped =
+            serde_json::from_str(r#"{"timestamp":"2023-12-14T13:02:00+01:00"}"#).unwrap();
+        assert_eq!(
+            parsed.timestamp,
+            Utc.with_ymd_and_hms(2023, 12, 14, 12, 2, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_datetime_rejects_garbage() {
+        let error = serde_json::from_str::<Timestamped>(r#"{"timestamp":"not a datetime"}"#)
+            .expect_err("garbage should not parse");
+
+        assert!(error.to_string().contains("Invalid datetime format"));
+    }
 }
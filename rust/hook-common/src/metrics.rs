@@ -4,6 +4,8 @@ use axum::{
     body::Body, extract::MatchedPath, http::Request, middleware::Next, response::IntoResponse,
     routing::get, Router,
 };
+use common_metrics::require_bearer_token;
+pub use metrics_exporter_prometheus::Matcher;
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 
 /// Bind a `TcpListener` on the provided bind address to serve a `Router` on it.
@@ -16,28 +18,65 @@ pub async fn serve(router: Router, bind: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// Options to customize the routes added by `setup_metrics_routes_with_options`.
+#[derive(Default)]
+pub struct MetricsOptions<'a> {
+    /// Histograms matching one of these use the associated bucket boundaries instead of the
+    /// defaults. `Matcher::Full` entries take precedence over `Matcher::Prefix` entries, which
+    /// take precedence over `Matcher::Suffix` entries.
+    pub histogram_buckets: &'a [(Matcher, &'a [f64])],
+    /// If set, scrapes of `/metrics` must send an `Authorization: Bearer <token>` header, see
+    /// `common_metrics::require_bearer_token`. Other routes, like the readiness and liveness
+    /// probes, are left open.
+    pub bearer_token: Option<String>,
+}
+
 /// Add the prometheus endpoint and middleware to a router, should be called last.
 pub fn setup_metrics_routes(router: Router) -> Router {
-    let recorder_handle = setup_metrics_recorder();
+    setup_metrics_routes_with_options(router, MetricsOptions::default())
+}
+
+/// Like `setup_metrics_routes`, customized with the provided `MetricsOptions`.
+pub fn setup_metrics_routes_with_options(router: Router, options: MetricsOptions) -> Router {
+    let recorder_handle = setup_metrics_recorder_with_buckets(options.histogram_buckets);
 
     router
-        .route(
-            "/metrics",
-            get(move || std::future::ready(recorder_handle.render())),
-        )
+        .merge(metrics_router(recorder_handle, options.bearer_token))
         .layer(axum::middleware::from_fn(track_metrics))
 }
 
+fn metrics_router(recorder_handle: PrometheusHandle, bearer_token: Option<String>) -> Router {
+    let router = Router::new().route(
+        "/metrics",
+        get(move || std::future::ready(recorder_handle.render())),
+    );
+
+    require_bearer_token(router, bearer_token)
+}
+
 pub fn setup_metrics_recorder() -> PrometheusHandle {
+    setup_metrics_recorder_with_buckets(&[])
+}
+
+pub fn setup_metrics_recorder_with_buckets(buckets: &[(Matcher, &[f64])]) -> PrometheusHandle {
+    metrics_builder(buckets).install_recorder().unwrap()
+}
+
+fn metrics_builder(buckets: &[(Matcher, &[f64])]) -> PrometheusBuilder {
     const EXPONENTIAL_SECONDS: &[f64] = &[
         0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
     ];
 
-    PrometheusBuilder::new()
-        .set_buckets(EXPONENTIAL_SECONDS)
-        .unwrap()
-        .install_recorder()
-        .unwrap()
+    buckets.iter().fold(
+        PrometheusBuilder::new()
+            .set_buckets(EXPONENTIAL_SECONDS)
+            .unwrap(),
+        |builder, (matcher, values)| {
+            builder
+                .set_buckets_for_metric(matcher.clone(), values)
+                .unwrap()
+        },
+    )
 }
 
 /// Middleware to record some common HTTP metrics
@@ -80,3 +119,95 @@ pub fn get_current_timestamp_seconds() -> f64 {
         .unwrap_or_default()
         .as_secs() as f64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, StatusCode};
+    use tower::ServiceExt; // for `call`, `oneshot`, and `ready`
+
+    #[test]
+    fn test_custom_histogram_buckets() {
+        let recorder = metrics_builder(&[
+            (
+                Matcher::Full("grpc_latency_seconds".to_owned()),
+                &[0.0001, 0.001],
+            ),
+            (Matcher::Prefix("grpc_".to_owned()), &[1.0]),
+        ])
+        .build_recorder();
+        let handle = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::histogram!("grpc_latency_seconds").record(0.0005);
+            metrics::histogram!("grpc_size_bytes").record(0.5);
+            metrics::histogram!("http_requests_duration_seconds").record(0.5);
+        });
+
+        let rendered = handle.render();
+        assert!(rendered.contains(r#"grpc_latency_seconds_bucket{le="0.0001"} 0"#));
+        assert!(rendered.contains(r#"grpc_latency_seconds_bucket{le="0.001"} 1"#));
+        assert!(!rendered.contains(r#"grpc_latency_seconds_bucket{le="0.005"}"#));
+        assert!(rendered.contains(r#"grpc_size_bytes_bucket{le="1"} 1"#));
+        assert!(!rendered.contains(r#"grpc_size_bytes_bucket{le="0.005"}"#));
+        assert!(rendered.contains(r#"http_requests_duration_seconds_bucket{le="0.005"} 0"#));
+    }
+
+    async fn scrape(router: Router, authorization: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri("/metrics");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+
+        router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_without_bearer_token() {
+        let handle = metrics_builder(&[]).build_recorder().handle();
+        let router = metrics_router(handle, None);
+
+        assert_eq!(scrape(router, None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_with_bearer_token() {
+        let handle = metrics_builder(&[]).build_recorder().handle();
+        let router = Router::new()
+            .route("/_readiness", get(|| async { "ok" }))
+            .merge(metrics_router(handle, Some("secret".to_owned())));
+
+        assert_eq!(
+            scrape(router.clone(), Some("Bearer secret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            scrape(router.clone(), Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(scrape(router.clone(), None).await, StatusCode::UNAUTHORIZED);
+
+        let probe = router
+            .oneshot(
+                Request::builder()
+                    .uri("/_readiness")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(probe.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_with_empty_bearer_token() {
+        let handle = metrics_builder(&[]).build_recorder().handle();
+        let router = metrics_router(handle, Some("".to_owned()));
+
+        assert_eq!(scrape(router, None).await, StatusCode::OK);
+    }
+}
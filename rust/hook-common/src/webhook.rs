@@ -209,6 +209,20 @@ impl WebhookJobError {
         }
     }
 
+    pub fn new_rate_limited(message: &str) -> Self {
+        let error_details = app_metrics::Error {
+            name: "Rate Limited".to_owned(),
+            message: Some(message.to_owned()),
+            stack: None,
+        };
+        Self {
+            r#type: app_metrics::ErrorType::RateLimited,
+            details: app_metrics::ErrorDetails {
+                error: error_details,
+            },
+        }
+    }
+
     pub fn new_parse(message: &str) -> Self {
         let error_details = app_metrics::Error {
             name: "Parse Error".to_owned(),
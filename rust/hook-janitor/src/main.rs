@@ -1,7 +1,6 @@
 use axum::Router;
 use cleanup::{Cleaner, CleanerModeName};
 use config::Config;
-use envconfig::Envconfig;
 use eyre::Result;
 use futures::future::{select, Either};
 use health::{HealthHandle, HealthRegistry};
@@ -10,7 +9,9 @@ use std::{str::FromStr, time::Duration};
 use tokio::sync::Semaphore;
 use webhooks::WebhookCleaner;
 
-use hook_common::metrics::setup_metrics_routes;
+use common_config::load_config;
+use common_metrics::setup_panic_hook;
+use hook_common::metrics::{setup_metrics_routes_with_options, MetricsOptions};
 
 mod cleanup;
 mod config;
@@ -41,9 +42,10 @@ async fn cleanup_loop(cleaner: Box<dyn Cleaner>, interval_secs: u64, liveness: H
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    let config: Config = load_config().expect("failed to load configuration");
 
-    let config = Config::init_from_env().expect("failed to load configuration from env");
+    common_logging::init(config.log_format);
+    setup_panic_hook();
 
     let mode_name = CleanerModeName::from_str(&config.mode)
         .unwrap_or_else(|_| panic!("invalid cleaner mode: {}", config.mode));
@@ -82,7 +84,13 @@ async fn main() {
         cleanup_liveness,
     ));
 
-    let app = setup_metrics_routes(handlers::app(liveness));
+    let app = setup_metrics_routes_with_options(
+        handlers::app(liveness),
+        MetricsOptions {
+            bearer_token: config.metrics_bearer_token.clone(),
+            ..Default::default()
+        },
+    );
     let http_server = Box::pin(listen(app, config.bind()));
 
     match select(http_server, cleanup_loop).await {
@@ -7,7 +7,6 @@ use futures::future::join_all;
 use hook_common::webhook::WebhookJobError;
 use rdkafka::error::KafkaError;
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use serde_json::error::Error as SerdeError;
 use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions, Postgres};
 use sqlx::types::{chrono, Uuid};
 use sqlx::{Row, Transaction};
@@ -36,8 +35,6 @@ pub enum WebhookCleanerError {
     GetCompletedRowsError { error: sqlx::Error },
     #[error("failed to get failed rows: {error}")]
     GetFailedRowsError { error: sqlx::Error },
-    #[error("failed to serialize rows: {error}")]
-    SerializeRowsError { error: SerdeError },
     #[error("failed to produce to kafka: {error}")]
     KafkaProduceError { error: KafkaError },
     #[error("failed to produce to kafka (timeout)")]
@@ -298,20 +295,32 @@ impl WebhookCleaner {
             return Ok(());
         }
 
-        let payloads: Vec<String> = metrics
+        let payloads: Vec<(String, String)> = metrics
             .into_iter()
-            .map(|metric| serde_json::to_string(&metric))
-            .collect::<Result<Vec<String>, SerdeError>>()
-            .map_err(|e| WebhookCleanerError::SerializeRowsError { error: e })?;
+            .filter(|metric| match metric.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("dropping invalid app metric {:?}: {}", metric, e);
+                    metrics::counter!("app_metrics_dead_lettered", "outcome" => "invalid")
+                        .increment(1);
+                    false
+                }
+            })
+            .filter_map(|metric| {
+                metric
+                    .to_json_or_dead_letter()
+                    .map(|payload| (metric.kafka_key(), payload))
+            })
+            .collect();
 
         let mut delivery_futures = Vec::new();
 
-        for payload in payloads {
+        for (key, payload) in payloads {
             match self.kafka_producer.send_result(FutureRecord {
                 topic: self.app_metrics_topic.as_str(),
                 payload: Some(&payload),
                 partition: None,
-                key: None::<&str>,
+                key: Some(&key),
                 timestamp: None,
                 headers: None,
             }) {
@@ -1,12 +1,14 @@
 //! Consume `PgQueue` jobs to run webhook calls.
 use axum::routing::get;
 use axum::Router;
-use envconfig::Envconfig;
 use std::future::ready;
 
+use common_config::load_config;
+use common_metrics::setup_panic_hook;
 use health::HealthRegistry;
 use hook_common::{
-    metrics::serve, metrics::setup_metrics_routes, pgqueue::PgQueue, retry::RetryPolicy,
+    metrics::serve, metrics::setup_metrics_routes_with_options, metrics::MetricsOptions,
+    pgqueue::PgQueue, retry::RetryPolicy,
 };
 use hook_worker::config::Config;
 use hook_worker::error::WorkerError;
@@ -14,9 +16,10 @@ use hook_worker::worker::WebhookWorker;
 
 #[tokio::main]
 async fn main() -> Result<(), WorkerError> {
-    tracing_subscriber::fmt::init();
+    let config: Config = load_config().expect("Invalid configuration:");
 
-    let config = Config::init_from_env().expect("Invalid configuration:");
+    common_logging::init(config.log_format);
+    setup_panic_hook();
 
     let liveness = HealthRegistry::new("liveness");
     let worker_liveness = liveness
@@ -60,7 +63,13 @@ async fn main() -> Result<(), WorkerError> {
         .route("/", get(index))
         .route("/_readiness", get(index))
         .route("/_liveness", get(move || ready(liveness.get_status())));
-    let router = setup_metrics_routes(router);
+    let router = setup_metrics_routes_with_options(
+        router,
+        MetricsOptions {
+            bearer_token: config.metrics_bearer_token.clone(),
+            ..Default::default()
+        },
+    );
     let bind = config.bind();
     tokio::task::spawn(async move {
         serve(router, &bind)
@@ -495,6 +495,7 @@ mod tests {
     // This is due to a long-standing cargo bug that reports imports and helper functions as unused.
     // See: https://github.com/rust-lang/rust/issues/46379.
     use health::HealthRegistry;
+    use hook_common::kafka_messages::app_metrics::ErrorType;
     use hook_common::pgqueue::{DatabaseError, NewJob};
     use sqlx::PgPool;
 
@@ -687,6 +688,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rate_limited_requests_are_reported_as_bad_http_status() {
+        // `ErrorType::RateLimited` can only be written once every janitor can deserialize it, so
+        // until then a 429 is reported like any other unsuccessful status code.
+        let response = reqwest::Response::from(
+            http::Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .body("")
+                .unwrap(),
+        );
+        let error = response
+            .error_for_status()
+            .expect_err("429 should be an error status");
+        let request_error = WebhookRequestError::RetryableRequestError {
+            error,
+            response: None,
+            retry_after: None,
+        };
+
+        let job_error = WebhookJobError::from(&request_error);
+
+        assert_eq!(job_error.r#type, ErrorType::BadHttpStatus(429));
+    }
+
     #[tokio::test]
     async fn test_private_ips_denied() {
         let method = HttpMethod::POST;